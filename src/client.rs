@@ -0,0 +1,14 @@
+use derive_more::Display;
+
+use super::{cpu::Cpu, device::Device, engine::Engine, os::OS, user_agent::UserAgent};
+
+/// The full set of information extracted from a user agent string.
+#[derive(Clone, Debug, Default, Display, PartialEq)]
+#[display(fmt = "{}/{}/{}", device, os, user_agent)]
+pub struct Client {
+    pub device: Device,
+    pub os: OS,
+    pub user_agent: UserAgent,
+    pub cpu: Cpu,
+    pub engine: Engine,
+}
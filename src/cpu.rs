@@ -0,0 +1,9 @@
+use derive_more::Display;
+
+/// The CPU architecture parsed from a user agent string (e.g. `amd64`,
+/// `arm64`, `wow64`, `ia32`).
+#[derive(Clone, Debug, Default, Display, Eq, PartialEq)]
+#[display(fmt = "{}", architecture)]
+pub struct Cpu {
+    pub architecture: String,
+}
@@ -0,0 +1,10 @@
+use derive_more::Display;
+
+/// The device information parsed from a user agent string.
+#[derive(Clone, Debug, Default, Display, Eq, PartialEq)]
+#[display(fmt = "{}", family)]
+pub struct Device {
+    pub family: String,
+    pub brand: Option<String>,
+    pub model: Option<String>,
+}
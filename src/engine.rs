@@ -0,0 +1,12 @@
+use derive_more::Display;
+
+/// The browser's layout/rendering engine parsed from a user agent string
+/// (e.g. Gecko, Blink/WebKit, Trident, Presto) and its version.
+#[derive(Clone, Debug, Default, Display, Eq, PartialEq)]
+#[display(fmt = "{}", family)]
+pub struct Engine {
+    pub family: String,
+    pub major: Option<String>,
+    pub minor: Option<String>,
+    pub patch: Option<String>,
+}
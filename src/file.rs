@@ -0,0 +1,59 @@
+use serde::Deserialize;
+
+/// The plain-data shape of the uap-core `regexes.yaml` dataset. This is
+/// deliberately a dumb `serde::Deserialize` target with no behavior of its
+/// own, so it can be produced from whichever serde-compatible format a
+/// caller prefers.
+#[derive(Debug, Deserialize)]
+pub struct RegexFile {
+    pub device_parsers: Vec<DeviceParserEntry>,
+    pub os_parsers: Vec<OSParserEntry>,
+    pub user_agent_parsers: Vec<UserAgentParserEntry>,
+    #[serde(default)]
+    pub cpu_parsers: Vec<CPUParserEntry>,
+    #[serde(default)]
+    pub engine_parsers: Vec<EngineParserEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeviceParserEntry {
+    pub regex: String,
+    pub regex_flag: Option<String>,
+    pub device_replacement: Option<String>,
+    pub brand_replacement: Option<String>,
+    pub model_replacement: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OSParserEntry {
+    pub regex: String,
+    pub os_replacement: Option<String>,
+    pub os_v1_replacement: Option<String>,
+    pub os_v2_replacement: Option<String>,
+    pub os_v3_replacement: Option<String>,
+    pub os_v4_replacement: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserAgentParserEntry {
+    pub regex: String,
+    pub family_replacement: Option<String>,
+    pub v1_replacement: Option<String>,
+    pub v2_replacement: Option<String>,
+    pub v3_replacement: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CPUParserEntry {
+    pub regex: String,
+    pub arch_replacement: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EngineParserEntry {
+    pub regex: String,
+    pub engine_replacement: Option<String>,
+    pub engine_v1_replacement: Option<String>,
+    pub engine_v2_replacement: Option<String>,
+    pub engine_v3_replacement: Option<String>,
+}
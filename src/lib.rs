@@ -0,0 +1,54 @@
+mod client;
+mod cpu;
+mod device;
+mod engine;
+mod file;
+mod os;
+mod parser;
+mod prefilter;
+mod user_agent;
+
+pub use client::Client;
+pub use cpu::Cpu;
+pub use device::Device;
+pub use engine::Engine;
+pub use file::{
+    CPUParserEntry, DeviceParserEntry, EngineParserEntry, OSParserEntry, RegexFile,
+    UserAgentParserEntry,
+};
+pub use os::OS;
+pub use parser::{Error, UserAgentParser};
+#[cfg(feature = "yaml")]
+pub use parser::YamlError;
+pub use user_agent::UserAgent;
+
+/// Implemented by anything capable of turning a user agent string into the
+/// pieces of a `Client`.
+pub trait Parser {
+    /// Returns the full `Client` info when given a user agent string
+    fn parse(&self, user_agent: &str) -> Client;
+
+    /// Returns just the `Device` info when given a user agent string
+    fn parse_device(&self, user_agent: &str) -> Device;
+
+    /// Returns just the `OS` info when given a user agent string
+    fn parse_os(&self, user_agent: &str) -> OS;
+
+    /// Returns just the `UserAgent` info when given a user agent string
+    fn parse_user_agent(&self, user_agent: &str) -> UserAgent;
+
+    /// Returns just the `Cpu` info when given a user agent string
+    fn parse_cpu(&self, user_agent: &str) -> Cpu;
+
+    /// Returns just the `Engine` info when given a user agent string
+    fn parse_engine(&self, user_agent: &str) -> Engine;
+}
+
+/// Implemented by each of the per-category matchers (`device`, `os`,
+/// `user_agent`, `cpu`, `engine`) that `UserAgentParser` delegates to
+/// internally.
+pub(crate) trait SubParser {
+    type Item;
+
+    fn try_parse(&self, user_agent: &str) -> Option<Self::Item>;
+}
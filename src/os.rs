@@ -0,0 +1,12 @@
+use derive_more::Display;
+
+/// The operating system information parsed from a user agent string.
+#[derive(Clone, Debug, Default, Display, Eq, PartialEq)]
+#[display(fmt = "{}", family)]
+pub struct OS {
+    pub family: String,
+    pub major: Option<String>,
+    pub minor: Option<String>,
+    pub patch: Option<String>,
+    pub patch_minor: Option<String>,
+}
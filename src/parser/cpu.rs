@@ -0,0 +1,65 @@
+use derive_more::{Display, From};
+use fancy_regex::Regex;
+
+use super::{none_if_empty, replace};
+use crate::{
+    cpu::Cpu,
+    file::CPUParserEntry,
+    prefilter::{self, AtomExpr, LiteralExpr},
+    SubParser,
+};
+
+#[derive(Debug, Display, From)]
+pub enum Error {
+    Regex(fancy_regex::Error),
+}
+
+#[derive(Debug)]
+pub struct Matcher {
+    regex: Regex,
+    arch_replacement: Option<String>,
+    pub(super) required: AtomExpr,
+}
+
+impl SubParser for Matcher {
+    type Item = Cpu;
+
+    fn try_parse(&self, user_agent: &str) -> Option<Cpu> {
+        let captures = self.regex.captures(user_agent).ok()??;
+
+        let architecture = self
+            .arch_replacement
+            .as_ref()
+            .map(|arch| replace(arch, &captures))
+            .or_else(|| captures.get(1).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty)?;
+
+        Some(Cpu { architecture })
+    }
+}
+
+impl Matcher {
+    /// Called once the category-wide `aho-corasick` automaton has been
+    /// built from every matcher's `LiteralExpr`, to install this
+    /// matcher's interned requirement tree.
+    pub(super) fn set_required(&mut self, required: AtomExpr) {
+        self.required = required;
+    }
+}
+
+impl TryFrom<CPUParserEntry> for (Matcher, LiteralExpr) {
+    type Error = Error;
+
+    fn try_from(entry: CPUParserEntry) -> Result<(Matcher, LiteralExpr), Error> {
+        let literal_expr = prefilter::extract_literals(&entry.regex);
+        let regex = Regex::new(&entry.regex)?;
+
+        let matcher = Matcher {
+            regex,
+            arch_replacement: entry.arch_replacement.and_then(none_if_empty),
+            required: AtomExpr::Always,
+        };
+
+        Ok((matcher, literal_expr))
+    }
+}
@@ -0,0 +1,85 @@
+use derive_more::{Display, From};
+use fancy_regex::Regex;
+
+use super::{none_if_empty, replace};
+use crate::{
+    device::Device,
+    file::DeviceParserEntry,
+    prefilter::{self, AtomExpr, LiteralExpr},
+    SubParser,
+};
+
+#[derive(Debug, Display, From)]
+pub enum Error {
+    Regex(fancy_regex::Error),
+}
+
+#[derive(Debug)]
+pub struct Matcher {
+    regex: Regex,
+    device_replacement: Option<String>,
+    brand_replacement: Option<String>,
+    model_replacement: Option<String>,
+    pub(super) required: AtomExpr,
+}
+
+impl SubParser for Matcher {
+    type Item = Device;
+
+    fn try_parse(&self, user_agent: &str) -> Option<Device> {
+        let captures = self.regex.captures(user_agent).ok()??;
+
+        let family = self
+            .device_replacement
+            .as_ref()
+            .map(|device| replace(device, &captures))
+            .or_else(|| captures.get(1).map(|x| x.as_str().to_owned()))?;
+
+        let brand = self
+            .brand_replacement
+            .as_ref()
+            .map(|brand| replace(brand, &captures))
+            .and_then(none_if_empty);
+
+        let model = self
+            .model_replacement
+            .as_ref()
+            .map(|model| replace(model, &captures))
+            .or_else(|| captures.get(2).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty);
+
+        Some(Device {
+            family,
+            brand,
+            model,
+        })
+    }
+}
+
+impl Matcher {
+    /// Called once the category-wide `aho-corasick` automaton has been
+    /// built from every matcher's `LiteralExpr`, to install this
+    /// matcher's interned requirement tree.
+    pub(super) fn set_required(&mut self, required: AtomExpr) {
+        self.required = required;
+    }
+}
+
+impl TryFrom<DeviceParserEntry> for (Matcher, LiteralExpr) {
+    type Error = Error;
+
+    fn try_from(entry: DeviceParserEntry) -> Result<(Matcher, LiteralExpr), Error> {
+        let literal_expr = prefilter::extract_literals(&entry.regex);
+        let regex = Regex::new(&entry.regex)?;
+
+        let matcher = Matcher {
+            regex,
+            device_replacement: entry.device_replacement.and_then(none_if_empty),
+            brand_replacement: entry.brand_replacement.and_then(none_if_empty),
+            model_replacement: entry.model_replacement.and_then(none_if_empty),
+            required: AtomExpr::Always,
+        };
+
+        Ok((matcher, literal_expr))
+    }
+}
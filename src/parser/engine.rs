@@ -0,0 +1,96 @@
+use derive_more::{Display, From};
+use fancy_regex::Regex;
+
+use super::{none_if_empty, replace};
+use crate::{
+    engine::Engine,
+    file::EngineParserEntry,
+    prefilter::{self, AtomExpr, LiteralExpr},
+    SubParser,
+};
+
+#[derive(Debug, Display, From)]
+pub enum Error {
+    Regex(fancy_regex::Error),
+}
+
+#[derive(Debug)]
+pub struct Matcher {
+    regex: Regex,
+    engine_replacement: Option<String>,
+    engine_v1_replacement: Option<String>,
+    engine_v2_replacement: Option<String>,
+    engine_v3_replacement: Option<String>,
+    pub(super) required: AtomExpr,
+}
+
+impl SubParser for Matcher {
+    type Item = Engine;
+
+    fn try_parse(&self, user_agent: &str) -> Option<Engine> {
+        let captures = self.regex.captures(user_agent).ok()??;
+
+        let family = self
+            .engine_replacement
+            .as_ref()
+            .map(|engine| replace(engine, &captures))
+            .or_else(|| captures.get(1).map(|x| x.as_str().to_owned()))?;
+
+        let major = self
+            .engine_v1_replacement
+            .as_ref()
+            .map(|v| replace(v, &captures))
+            .or_else(|| captures.get(2).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty);
+
+        let minor = self
+            .engine_v2_replacement
+            .as_ref()
+            .map(|v| replace(v, &captures))
+            .or_else(|| captures.get(3).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty);
+
+        let patch = self
+            .engine_v3_replacement
+            .as_ref()
+            .map(|v| replace(v, &captures))
+            .or_else(|| captures.get(4).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty);
+
+        Some(Engine {
+            family,
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+impl Matcher {
+    /// Called once the category-wide `aho-corasick` automaton has been
+    /// built from every matcher's `LiteralExpr`, to install this
+    /// matcher's interned requirement tree.
+    pub(super) fn set_required(&mut self, required: AtomExpr) {
+        self.required = required;
+    }
+}
+
+impl TryFrom<EngineParserEntry> for (Matcher, LiteralExpr) {
+    type Error = Error;
+
+    fn try_from(entry: EngineParserEntry) -> Result<(Matcher, LiteralExpr), Error> {
+        let literal_expr = prefilter::extract_literals(&entry.regex);
+        let regex = Regex::new(&entry.regex)?;
+
+        let matcher = Matcher {
+            regex,
+            engine_replacement: entry.engine_replacement.and_then(none_if_empty),
+            engine_v1_replacement: entry.engine_v1_replacement.and_then(none_if_empty),
+            engine_v2_replacement: entry.engine_v2_replacement.and_then(none_if_empty),
+            engine_v3_replacement: entry.engine_v3_replacement.and_then(none_if_empty),
+            required: AtomExpr::Always,
+        };
+
+        Ok((matcher, literal_expr))
+    }
+}
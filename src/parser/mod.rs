@@ -1,31 +1,47 @@
 use derive_more::{Display, From};
 use rayon::prelude::*;
-use serde_yaml;
 
 use super::{
     client::Client,
+    cpu::Cpu,
     device::Device,
-    file::{DeviceParserEntry, OSParserEntry, RegexFile, UserAgentParserEntry},
+    engine::Engine,
+    file::RegexFile,
     os::OS,
     parser::{
-        device::Error as DeviceError, os::Error as OSError,
-        user_agent::Error as UserAgentError,
+        cpu::Error as CpuError, device::Error as DeviceError, engine::Error as EngineError,
+        os::Error as OSError, user_agent::Error as UserAgentError,
     },
+    prefilter::{self, LiteralExpr},
     user_agent::UserAgent,
     Parser, SubParser,
 };
 
+mod cpu;
 mod device;
+mod engine;
 mod os;
 mod user_agent;
 
 #[derive(Debug, Display, From)]
 pub enum Error {
-    IO(std::io::Error),
-    Yaml(serde_yaml::Error),
     Device(DeviceError),
     OS(OSError),
     UserAgent(UserAgentError),
+    Cpu(CpuError),
+    Engine(EngineError),
+}
+
+/// Errors specific to the `yaml`-feature convenience constructors, kept
+/// separate from `Error` so that building a `UserAgentParser` from an
+/// already-deserialized `RegexFile` never drags in a format-specific error
+/// type.
+#[cfg(feature = "yaml")]
+#[derive(Debug, Display, From)]
+pub enum YamlError {
+    IO(std::io::Error),
+    Yaml(serde_yaml::Error),
+    Parser(Error),
 }
 
 /// Handles the actual parsing of a user agent string by delegating to
@@ -33,29 +49,44 @@ pub enum Error {
 #[derive(Debug)]
 pub struct UserAgentParser {
     device_matchers: Vec<device::Matcher>,
+    device_automaton: Option<aho_corasick::AhoCorasick>,
     os_matchers: Vec<os::Matcher>,
+    os_automaton: Option<aho_corasick::AhoCorasick>,
     user_agent_matchers: Vec<user_agent::Matcher>,
+    user_agent_automaton: Option<aho_corasick::AhoCorasick>,
+    cpu_matchers: Vec<cpu::Matcher>,
+    cpu_automaton: Option<aho_corasick::AhoCorasick>,
+    engine_matchers: Vec<engine::Matcher>,
+    engine_automaton: Option<aho_corasick::AhoCorasick>,
 }
 
 impl Parser for UserAgentParser {
     /// Returns the full `Client` info when given a user agent string
     fn parse(&self, user_agent: &str) -> Client {
-        let device = self.parse_device(&user_agent);
-        let os = self.parse_os(&user_agent);
-        let user_agent = self.parse_user_agent(&user_agent);
+        let device = self.parse_device(user_agent);
+        let os = self.parse_os(user_agent);
+        let cpu = self.parse_cpu(user_agent);
+        let engine = self.parse_engine(user_agent);
+        let user_agent = self.parse_user_agent(user_agent);
 
         Client {
             device,
             os,
             user_agent,
+            cpu,
+            engine,
         }
     }
 
     /// Returns just the `Device` info when given a user agent string
     fn parse_device(&self, user_agent: &str) -> Device {
+        let lowered = user_agent.to_lowercase();
+        let present = prefilter::present_atoms(&self.device_automaton, &lowered);
+
         self.device_matchers
             .iter()
-            .filter_map(|matcher| matcher.try_parse(&user_agent))
+            .filter(|matcher| matcher.required.is_candidate(&present))
+            .filter_map(|matcher| matcher.try_parse(user_agent))
             .take(1)
             .next()
             .unwrap_or_default()
@@ -63,9 +94,13 @@ impl Parser for UserAgentParser {
 
     /// Returns just the `OS` info when given a user agent string
     fn parse_os(&self, user_agent: &str) -> OS {
+        let lowered = user_agent.to_lowercase();
+        let present = prefilter::present_atoms(&self.os_automaton, &lowered);
+
         self.os_matchers
             .iter()
-            .filter_map(|matcher| matcher.try_parse(&user_agent))
+            .filter(|matcher| matcher.required.is_candidate(&present))
+            .filter_map(|matcher| matcher.try_parse(user_agent))
             .take(1)
             .next()
             .unwrap_or_default()
@@ -73,20 +108,53 @@ impl Parser for UserAgentParser {
 
     /// Returns just the `UserAgent` info when given a user agent string
     fn parse_user_agent(&self, user_agent: &str) -> UserAgent {
+        let lowered = user_agent.to_lowercase();
+        let present = prefilter::present_atoms(&self.user_agent_automaton, &lowered);
+
         self.user_agent_matchers
             .iter()
-            .filter_map(|matcher| matcher.try_parse(&user_agent))
+            .filter(|matcher| matcher.required.is_candidate(&present))
+            .filter_map(|matcher| matcher.try_parse(user_agent))
+            .take(1)
+            .next()
+            .unwrap_or_default()
+    }
+
+    /// Returns just the `Cpu` info when given a user agent string
+    fn parse_cpu(&self, user_agent: &str) -> Cpu {
+        let lowered = user_agent.to_lowercase();
+        let present = prefilter::present_atoms(&self.cpu_automaton, &lowered);
+
+        self.cpu_matchers
+            .iter()
+            .filter(|matcher| matcher.required.is_candidate(&present))
+            .filter_map(|matcher| matcher.try_parse(user_agent))
+            .take(1)
+            .next()
+            .unwrap_or_default()
+    }
+
+    /// Returns just the `Engine` info when given a user agent string
+    fn parse_engine(&self, user_agent: &str) -> Engine {
+        let lowered = user_agent.to_lowercase();
+        let present = prefilter::present_atoms(&self.engine_automaton, &lowered);
+
+        self.engine_matchers
+            .iter()
+            .filter(|matcher| matcher.required.is_candidate(&present))
+            .filter_map(|matcher| matcher.try_parse(user_agent))
             .take(1)
             .next()
             .unwrap_or_default()
     }
 }
 
+#[cfg(feature = "yaml")]
 impl UserAgentParser {
     /// Attempts to construct a `UserAgentParser` from the path to a file
-    pub fn from_yaml(path: &str) -> Result<UserAgentParser, Error> {
+    pub fn from_yaml(path: &str) -> Result<UserAgentParser, YamlError> {
         let file = std::fs::File::open(path)?;
-        Ok(UserAgentParser::from_file(file)?)
+        UserAgentParser::from_file(file)
     }
 
     /// Attempts to construct a `UserAgentParser` from a slice of raw bytes. The
@@ -94,12 +162,12 @@ impl UserAgentParser {
     /// `include_bytes!` macro to compile the `regexes.yaml` file into the
     /// the library by a consuming application.
     ///
-    /// ```rust
+    /// ```rust,ignore
     /// # use uaparser::*;
     /// let regexes = include_bytes!("../../src/core/regexes.yaml");
     /// let parser = UserAgentParser::from_bytes(regexes);
     /// ```
-    pub fn from_bytes(bytes: &[u8]) -> Result<UserAgentParser, Error> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<UserAgentParser, YamlError> {
         let regex_file: RegexFile = serde_yaml::from_slice(bytes)?;
         Ok(UserAgentParser::try_from(regex_file)?)
     }
@@ -107,90 +175,215 @@ impl UserAgentParser {
     /// Attempts to construct a `UserAgentParser` from a reference to an open
     /// `File`. This `File` should be a the `regexes.yaml` depended on by
     /// all the various implementations of the UA Parser library.
-    pub fn from_file(file: std::fs::File) -> Result<UserAgentParser, Error> {
+    pub fn from_file(file: std::fs::File) -> Result<UserAgentParser, YamlError> {
         let regex_file: RegexFile = serde_yaml::from_reader(file)?;
         Ok(UserAgentParser::try_from(regex_file)?)
     }
+}
 
+impl UserAgentParser {
+    /// Attempts to construct a `UserAgentParser` from an already
+    /// deserialized `RegexFile`. This is the canonical construction path;
+    /// callers who ship the uap-core dataset in a format other than YAML
+    /// (JSON, CBOR, ...) can deserialize a `RegexFile` with their own serde
+    /// backend and hand the result in here directly.
     pub fn try_from(regex_file: RegexFile) -> Result<UserAgentParser, Error> {
-        let device_matchers = regex_file
+        let device_built = regex_file
             .device_parsers
             .into_par_iter()
             .try_fold(
-                || Vec::new(),
+                Vec::new,
                 |mut v, parser| -> Result<_, Error> {
-                    let matcher = device::Matcher::try_from(parser)?;
-                    v.push(matcher);
+                    v.push(<(device::Matcher, LiteralExpr)>::try_from(parser)?);
                     Ok(v)
                 },
             )
             .try_reduce(
-                || Vec::new(),
+                Vec::new,
                 |mut v1, v2| {
                     v1.extend(v2);
                     Ok(v1)
                 },
             )?;
 
-        let os_matchers = regex_file
+        let os_built = regex_file
             .os_parsers
             .into_par_iter()
             .try_fold(
-                || Vec::new(),
+                Vec::new,
                 |mut v, parser| -> Result<_, Error> {
-                    let matcher = os::Matcher::try_from(parser)?;
-                    v.push(matcher);
+                    v.push(<(os::Matcher, LiteralExpr)>::try_from(parser)?);
                     Ok(v)
                 },
             )
             .try_reduce(
-                || Vec::new(),
+                Vec::new,
                 |mut v1, v2| {
                     v1.extend(v2);
                     Ok(v1)
                 },
             )?;
 
-        let user_agent_matchers = regex_file
+        let user_agent_built = regex_file
             .user_agent_parsers
             .into_par_iter()
             .try_fold(
-                || Vec::new(),
+                Vec::new,
+                |mut v, parser| -> Result<_, Error> {
+                    v.push(<(user_agent::Matcher, LiteralExpr)>::try_from(parser)?);
+                    Ok(v)
+                },
+            )
+            .try_reduce(
+                Vec::new,
+                |mut v1, v2| {
+                    v1.extend(v2);
+                    Ok(v1)
+                },
+            )?;
+
+        let (mut device_matchers, device_literals): (Vec<_>, Vec<_>) =
+            device_built.into_iter().unzip();
+        let (device_automaton, device_atoms) = prefilter::build(&device_literals);
+        for (matcher, required) in device_matchers.iter_mut().zip(device_atoms) {
+            matcher.set_required(required);
+        }
+
+        let (mut os_matchers, os_literals): (Vec<_>, Vec<_>) = os_built.into_iter().unzip();
+        let (os_automaton, os_atoms) = prefilter::build(&os_literals);
+        for (matcher, required) in os_matchers.iter_mut().zip(os_atoms) {
+            matcher.set_required(required);
+        }
+
+        let (mut user_agent_matchers, user_agent_literals): (Vec<_>, Vec<_>) =
+            user_agent_built.into_iter().unzip();
+        let (user_agent_automaton, user_agent_atoms) = prefilter::build(&user_agent_literals);
+        for (matcher, required) in user_agent_matchers.iter_mut().zip(user_agent_atoms) {
+            matcher.set_required(required);
+        }
+
+        let cpu_built = regex_file
+            .cpu_parsers
+            .into_par_iter()
+            .try_fold(
+                Vec::new,
                 |mut v, parser| -> Result<_, Error> {
-                    let matcher = user_agent::Matcher::try_from(parser)?;
-                    v.push(matcher);
+                    v.push(<(cpu::Matcher, LiteralExpr)>::try_from(parser)?);
                     Ok(v)
                 },
             )
             .try_reduce(
-                || Vec::new(),
+                Vec::new,
                 |mut v1, v2| {
                     v1.extend(v2);
                     Ok(v1)
                 },
             )?;
 
-        // for parser in regex_file.device_parsers.into_iter() {
-        //     device_matchers.push(device::Matcher::try_from(parser)?);
-        // }
+        let engine_built = regex_file
+            .engine_parsers
+            .into_par_iter()
+            .try_fold(
+                Vec::new,
+                |mut v, parser| -> Result<_, Error> {
+                    v.push(<(engine::Matcher, LiteralExpr)>::try_from(parser)?);
+                    Ok(v)
+                },
+            )
+            .try_reduce(
+                Vec::new,
+                |mut v1, v2| {
+                    v1.extend(v2);
+                    Ok(v1)
+                },
+            )?;
 
-        // for parser in regex_file.os_parsers.into_iter() {
-        //     os_matchers.push(os::Matcher::try_from(parser)?);
-        // }
+        let (mut cpu_matchers, cpu_literals): (Vec<_>, Vec<_>) = cpu_built.into_iter().unzip();
+        let (cpu_automaton, cpu_atoms) = prefilter::build(&cpu_literals);
+        for (matcher, required) in cpu_matchers.iter_mut().zip(cpu_atoms) {
+            matcher.set_required(required);
+        }
 
-        // for parser in regex_file.user_agent_parsers.into_iter() {
-        //     user_agent_matchers.push(user_agent::Matcher::try_from(parser)?);
-        // }
+        let (mut engine_matchers, engine_literals): (Vec<_>, Vec<_>) =
+            engine_built.into_iter().unzip();
+        let (engine_automaton, engine_atoms) = prefilter::build(&engine_literals);
+        for (matcher, required) in engine_matchers.iter_mut().zip(engine_atoms) {
+            matcher.set_required(required);
+        }
 
         Ok(UserAgentParser {
             device_matchers,
+            device_automaton,
             os_matchers,
+            os_automaton,
             user_agent_matchers,
+            user_agent_automaton,
+            cpu_matchers,
+            cpu_automaton,
+            engine_matchers,
+            engine_automaton,
         })
     }
+
+    /// Parses a batch of user agent strings in parallel via rayon's
+    /// `par_iter`, distributing the strings across threads. Intended for
+    /// offline analytics jobs classifying large volumes of logged user
+    /// agent strings, where driving the loop inside the crate lets future
+    /// versions share per-thread prefilter scratch state.
+    pub fn parse_batch(&self, user_agents: &[&str]) -> Vec<Client> {
+        user_agents
+            .par_iter()
+            .map(|user_agent| self.parse(user_agent))
+            .collect()
+    }
+
+    /// Parses just the `Device` info for a batch of user agent strings in
+    /// parallel. See [`UserAgentParser::parse_batch`].
+    pub fn parse_device_batch(&self, user_agents: &[&str]) -> Vec<Device> {
+        user_agents
+            .par_iter()
+            .map(|user_agent| self.parse_device(user_agent))
+            .collect()
+    }
+
+    /// Parses just the `OS` info for a batch of user agent strings in
+    /// parallel. See [`UserAgentParser::parse_batch`].
+    pub fn parse_os_batch(&self, user_agents: &[&str]) -> Vec<OS> {
+        user_agents
+            .par_iter()
+            .map(|user_agent| self.parse_os(user_agent))
+            .collect()
+    }
+
+    /// Parses just the `UserAgent` info for a batch of user agent strings
+    /// in parallel. See [`UserAgentParser::parse_batch`].
+    pub fn parse_user_agent_batch(&self, user_agents: &[&str]) -> Vec<UserAgent> {
+        user_agents
+            .par_iter()
+            .map(|user_agent| self.parse_user_agent(user_agent))
+            .collect()
+    }
+
+    /// Parses just the `Cpu` info for a batch of user agent strings in
+    /// parallel. See [`UserAgentParser::parse_batch`].
+    pub fn parse_cpu_batch(&self, user_agents: &[&str]) -> Vec<Cpu> {
+        user_agents
+            .par_iter()
+            .map(|user_agent| self.parse_cpu(user_agent))
+            .collect()
+    }
+
+    /// Parses just the `Engine` info for a batch of user agent strings in
+    /// parallel. See [`UserAgentParser::parse_batch`].
+    pub fn parse_engine_batch(&self, user_agents: &[&str]) -> Vec<Engine> {
+        user_agents
+            .par_iter()
+            .map(|user_agent| self.parse_engine(user_agent))
+            .collect()
+    }
 }
 
-pub(self) fn none_if_empty<T: AsRef<str>>(s: T) -> Option<T> {
+fn none_if_empty<T: AsRef<str>>(s: T) -> Option<T> {
     if !s.as_ref().is_empty() {
         Some(s)
     } else {
@@ -198,12 +391,12 @@ pub(self) fn none_if_empty<T: AsRef<str>>(s: T) -> Option<T> {
     }
 }
 
-pub(self) fn replace(replacement: &str, captures: &fancy_regex::Captures) -> String {
+fn replace(replacement: &str, captures: &fancy_regex::Captures) -> String {
     if replacement.contains('$') && captures.len() > 0 {
         (1..=captures.len())
             .fold(replacement.to_owned(), |state: String, i: usize| {
                 let group = captures.get(i).map(|x| x.as_str()).unwrap_or("");
-                state.replace(&format!("${}", i), &group)
+                state.replace(&format!("${}", i), group)
             })
             .trim()
             .to_owned()
@@ -211,3 +404,64 @@ pub(self) fn replace(replacement: &str, captures: &fancy_regex::Captures) -> Str
         replacement.to_owned()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::{CPUParserEntry, EngineParserEntry, UserAgentParserEntry};
+
+    fn test_regex_file() -> RegexFile {
+        RegexFile {
+            device_parsers: Vec::new(),
+            os_parsers: Vec::new(),
+            user_agent_parsers: vec![UserAgentParserEntry {
+                regex: r"Safari/(\d+)".to_owned(),
+                family_replacement: Some("Safari".to_owned()),
+                v1_replacement: None,
+                v2_replacement: None,
+                v3_replacement: None,
+            }],
+            cpu_parsers: vec![CPUParserEntry {
+                regex: r"(amd64|x86_64)".to_owned(),
+                arch_replacement: Some("amd64".to_owned()),
+            }],
+            engine_parsers: vec![EngineParserEntry {
+                regex: r"Gecko/(\d+)".to_owned(),
+                engine_replacement: Some("Gecko".to_owned()),
+                engine_v1_replacement: None,
+                engine_v2_replacement: None,
+                engine_v3_replacement: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn parse_cpu_finds_architecture() {
+        let parser = UserAgentParser::try_from(test_regex_file()).unwrap();
+        let cpu = parser.parse_cpu("Mozilla/5.0 (X11; Linux x86_64) Safari/605.1");
+
+        assert_eq!(cpu.architecture, "amd64");
+    }
+
+    #[test]
+    fn parse_engine_finds_family() {
+        let parser = UserAgentParser::try_from(test_regex_file()).unwrap();
+        let engine = parser.parse_engine("Mozilla/5.0 Gecko/20100101 Safari/605.1");
+
+        assert_eq!(engine.family, "Gecko");
+    }
+
+    #[test]
+    fn parse_batch_matches_sequential_parse() {
+        let parser = UserAgentParser::try_from(test_regex_file()).unwrap();
+        let user_agents = [
+            "Mozilla/5.0 (X11; Linux x86_64) Gecko/20100101 Safari/605.1",
+            "Mozilla/5.0 (iPhone)",
+        ];
+
+        let expected: Vec<_> = user_agents.iter().map(|ua| parser.parse(ua)).collect();
+        let actual = parser.parse_batch(&user_agents);
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,106 @@
+use derive_more::{Display, From};
+use fancy_regex::Regex;
+
+use super::{none_if_empty, replace};
+use crate::{
+    file::OSParserEntry,
+    os::OS,
+    prefilter::{self, AtomExpr, LiteralExpr},
+    SubParser,
+};
+
+#[derive(Debug, Display, From)]
+pub enum Error {
+    Regex(fancy_regex::Error),
+}
+
+#[derive(Debug)]
+pub struct Matcher {
+    regex: Regex,
+    os_replacement: Option<String>,
+    os_v1_replacement: Option<String>,
+    os_v2_replacement: Option<String>,
+    os_v3_replacement: Option<String>,
+    os_v4_replacement: Option<String>,
+    pub(super) required: AtomExpr,
+}
+
+impl SubParser for Matcher {
+    type Item = OS;
+
+    fn try_parse(&self, user_agent: &str) -> Option<OS> {
+        let captures = self.regex.captures(user_agent).ok()??;
+
+        let family = self
+            .os_replacement
+            .as_ref()
+            .map(|os| replace(os, &captures))
+            .or_else(|| captures.get(1).map(|x| x.as_str().to_owned()))?;
+
+        let major = self
+            .os_v1_replacement
+            .as_ref()
+            .map(|v| replace(v, &captures))
+            .or_else(|| captures.get(2).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty);
+
+        let minor = self
+            .os_v2_replacement
+            .as_ref()
+            .map(|v| replace(v, &captures))
+            .or_else(|| captures.get(3).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty);
+
+        let patch = self
+            .os_v3_replacement
+            .as_ref()
+            .map(|v| replace(v, &captures))
+            .or_else(|| captures.get(4).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty);
+
+        let patch_minor = self
+            .os_v4_replacement
+            .as_ref()
+            .map(|v| replace(v, &captures))
+            .or_else(|| captures.get(5).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty);
+
+        Some(OS {
+            family,
+            major,
+            minor,
+            patch,
+            patch_minor,
+        })
+    }
+}
+
+impl Matcher {
+    /// Called once the category-wide `aho-corasick` automaton has been
+    /// built from every matcher's `LiteralExpr`, to install this
+    /// matcher's interned requirement tree.
+    pub(super) fn set_required(&mut self, required: AtomExpr) {
+        self.required = required;
+    }
+}
+
+impl TryFrom<OSParserEntry> for (Matcher, LiteralExpr) {
+    type Error = Error;
+
+    fn try_from(entry: OSParserEntry) -> Result<(Matcher, LiteralExpr), Error> {
+        let literal_expr = prefilter::extract_literals(&entry.regex);
+        let regex = Regex::new(&entry.regex)?;
+
+        let matcher = Matcher {
+            regex,
+            os_replacement: entry.os_replacement.and_then(none_if_empty),
+            os_v1_replacement: entry.os_v1_replacement.and_then(none_if_empty),
+            os_v2_replacement: entry.os_v2_replacement.and_then(none_if_empty),
+            os_v3_replacement: entry.os_v3_replacement.and_then(none_if_empty),
+            os_v4_replacement: entry.os_v4_replacement.and_then(none_if_empty),
+            required: AtomExpr::Always,
+        };
+
+        Ok((matcher, literal_expr))
+    }
+}
@@ -0,0 +1,96 @@
+use derive_more::{Display, From};
+use fancy_regex::Regex;
+
+use super::{none_if_empty, replace};
+use crate::{
+    file::UserAgentParserEntry,
+    prefilter::{self, AtomExpr, LiteralExpr},
+    user_agent::UserAgent,
+    SubParser,
+};
+
+#[derive(Debug, Display, From)]
+pub enum Error {
+    Regex(fancy_regex::Error),
+}
+
+#[derive(Debug)]
+pub struct Matcher {
+    regex: Regex,
+    family_replacement: Option<String>,
+    v1_replacement: Option<String>,
+    v2_replacement: Option<String>,
+    v3_replacement: Option<String>,
+    pub(super) required: AtomExpr,
+}
+
+impl SubParser for Matcher {
+    type Item = UserAgent;
+
+    fn try_parse(&self, user_agent: &str) -> Option<UserAgent> {
+        let captures = self.regex.captures(user_agent).ok()??;
+
+        let family = self
+            .family_replacement
+            .as_ref()
+            .map(|family| replace(family, &captures))
+            .or_else(|| captures.get(1).map(|x| x.as_str().to_owned()))?;
+
+        let major = self
+            .v1_replacement
+            .as_ref()
+            .map(|v| replace(v, &captures))
+            .or_else(|| captures.get(2).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty);
+
+        let minor = self
+            .v2_replacement
+            .as_ref()
+            .map(|v| replace(v, &captures))
+            .or_else(|| captures.get(3).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty);
+
+        let patch = self
+            .v3_replacement
+            .as_ref()
+            .map(|v| replace(v, &captures))
+            .or_else(|| captures.get(4).map(|x| x.as_str().to_owned()))
+            .and_then(none_if_empty);
+
+        Some(UserAgent {
+            family,
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+impl Matcher {
+    /// Called once the category-wide `aho-corasick` automaton has been
+    /// built from every matcher's `LiteralExpr`, to install this
+    /// matcher's interned requirement tree.
+    pub(super) fn set_required(&mut self, required: AtomExpr) {
+        self.required = required;
+    }
+}
+
+impl TryFrom<UserAgentParserEntry> for (Matcher, LiteralExpr) {
+    type Error = Error;
+
+    fn try_from(entry: UserAgentParserEntry) -> Result<(Matcher, LiteralExpr), Error> {
+        let literal_expr = prefilter::extract_literals(&entry.regex);
+        let regex = Regex::new(&entry.regex)?;
+
+        let matcher = Matcher {
+            regex,
+            family_replacement: entry.family_replacement.and_then(none_if_empty),
+            v1_replacement: entry.v1_replacement.and_then(none_if_empty),
+            v2_replacement: entry.v2_replacement.and_then(none_if_empty),
+            v3_replacement: entry.v3_replacement.and_then(none_if_empty),
+            required: AtomExpr::Always,
+        };
+
+        Ok((matcher, literal_expr))
+    }
+}
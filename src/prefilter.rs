@@ -0,0 +1,211 @@
+//! A literal-substring prefilter used to avoid running every regex in a
+//! matcher category against every user agent string.
+//!
+//! For each pattern we derive a boolean tree over the mandatory literal
+//! substrings ("atoms") that must appear in any string the pattern can
+//! match: a concatenation requires ALL of its parts' atoms, an alternation
+//! requires ANY branch's atoms, a repeat with a minimum of at least one
+//! requires its child's atoms, and anything the walker can't reason about
+//! (a quantifier that can match zero times, lookaround, backreferences,
+//! etc.) contributes no atom, which makes that sub-expression trivially
+//! satisfiable. The atoms collected across a whole matcher category are
+//! compiled into a single `aho-corasick` automaton; a single scan of an
+//! input string over that automaton tells us, for every pattern, whether
+//! it's even worth trying.
+use std::collections::{HashMap, HashSet};
+
+use aho_corasick::AhoCorasick;
+
+pub type AtomId = usize;
+
+/// A literal requirement tree keyed by raw atom text, produced directly
+/// from walking a regex's structure.
+#[derive(Debug, Clone)]
+pub enum LiteralExpr {
+    Literal(String),
+    All(Vec<LiteralExpr>),
+    Any(Vec<LiteralExpr>),
+    /// No mandatory literal could be derived; treat as always satisfiable.
+    Always,
+}
+
+/// The same tree, with each literal interned into an `AtomId` from a
+/// shared `aho-corasick` automaton.
+#[derive(Debug, Clone)]
+pub enum AtomExpr {
+    Atom(AtomId),
+    All(Vec<AtomExpr>),
+    Any(Vec<AtomExpr>),
+    Always,
+}
+
+impl AtomExpr {
+    /// Evaluates this matcher's requirement against the set of atom ids
+    /// found present in a scanned string.
+    pub fn is_candidate(&self, present: &HashSet<AtomId>) -> bool {
+        match self {
+            AtomExpr::Atom(id) => present.contains(id),
+            AtomExpr::All(parts) => parts.iter().all(|part| part.is_candidate(present)),
+            AtomExpr::Any(parts) => parts.iter().any(|part| part.is_candidate(present)),
+            AtomExpr::Always => true,
+        }
+    }
+}
+
+/// Walks the structure of `pattern` and derives the `LiteralExpr`
+/// describing its mandatory literal substrings. uap-core patterns are
+/// matched case-insensitively in practice, so atoms are lowercased;
+/// callers must lowercase the scanned input to match.
+pub fn extract_literals(pattern: &str) -> LiteralExpr {
+    match fancy_regex::Expr::parse_tree(pattern) {
+        Ok(tree) => literals_of(&tree.expr),
+        Err(_) => LiteralExpr::Always,
+    }
+}
+
+fn literals_of(expr: &fancy_regex::Expr) -> LiteralExpr {
+    use fancy_regex::Expr;
+
+    match expr {
+        Expr::Literal { val, .. } if !val.is_empty() => LiteralExpr::Literal(val.to_lowercase()),
+        Expr::Concat(subs) => LiteralExpr::All(subs.iter().map(literals_of).collect()),
+        Expr::Alt(subs) => {
+            let parts: Vec<_> = subs.iter().map(literals_of).collect();
+            if parts.iter().any(|part| matches!(part, LiteralExpr::Always)) {
+                LiteralExpr::Always
+            } else {
+                LiteralExpr::Any(parts)
+            }
+        }
+        Expr::Group(child) => literals_of(child),
+        Expr::Repeat { child, lo, .. } if *lo > 0 => literals_of(child),
+        // Zero-width quantifiers (`*`, `?`, `{0,n}`), lookaround,
+        // backreferences, and anything else we don't special-case are
+        // trivially satisfiable: we can't rule a string out, so don't try.
+        _ => LiteralExpr::Always,
+    }
+}
+
+/// Builds a single `aho-corasick` automaton over the union of atoms
+/// referenced by `trees`, and rewrites each tree into an `AtomExpr`
+/// indexing into it. Returns `None` for the automaton when no pattern in
+/// the category yielded any atom at all.
+pub fn build(trees: &[LiteralExpr]) -> (Option<AhoCorasick>, Vec<AtomExpr>) {
+    let mut ids: HashMap<String, AtomId> = HashMap::new();
+    let mut atoms: Vec<String> = Vec::new();
+
+    fn collect(tree: &LiteralExpr, ids: &mut HashMap<String, AtomId>, atoms: &mut Vec<String>) {
+        match tree {
+            LiteralExpr::Literal(atom) => {
+                if !ids.contains_key(atom) {
+                    ids.insert(atom.clone(), atoms.len());
+                    atoms.push(atom.clone());
+                }
+            }
+            LiteralExpr::All(parts) | LiteralExpr::Any(parts) => {
+                for part in parts {
+                    collect(part, ids, atoms);
+                }
+            }
+            LiteralExpr::Always => {}
+        }
+    }
+
+    for tree in trees {
+        collect(tree, &mut ids, &mut atoms);
+    }
+
+    fn intern(tree: &LiteralExpr, ids: &HashMap<String, AtomId>) -> AtomExpr {
+        match tree {
+            LiteralExpr::Literal(atom) => AtomExpr::Atom(ids[atom]),
+            LiteralExpr::All(parts) => AtomExpr::All(parts.iter().map(|p| intern(p, ids)).collect()),
+            LiteralExpr::Any(parts) => AtomExpr::Any(parts.iter().map(|p| intern(p, ids)).collect()),
+            LiteralExpr::Always => AtomExpr::Always,
+        }
+    }
+
+    let atom_exprs = trees.iter().map(|tree| intern(tree, &ids)).collect();
+
+    let automaton = if atoms.is_empty() {
+        None
+    } else {
+        Some(AhoCorasick::new(&atoms).expect("a finite set of literal atoms always compiles"))
+    };
+
+    (automaton, atom_exprs)
+}
+
+/// Scans `lowered_user_agent` (already lowercased by the caller) once and
+/// returns the set of atom ids present in it.
+///
+/// This must use `find_overlapping_iter`, not `find_iter`: `find_iter`
+/// only reports non-overlapping matches and resumes scanning after each
+/// match it reports, so an atom that starts before a previously-reported
+/// match ends (e.g. atoms `"mac os"` and `"os x"` against the input
+/// `"mac os x"`) would never be reported, silently marking a matcher that
+/// requires it as not a candidate.
+pub fn present_atoms(automaton: &Option<AhoCorasick>, lowered_user_agent: &str) -> HashSet<AtomId> {
+    match automaton {
+        Some(automaton) => automaton
+            .find_overlapping_iter(lowered_user_agent)
+            .map(|m| m.pattern().as_usize())
+            .collect(),
+        None => HashSet::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_atoms_are_both_detected() {
+        // "mac os" and "os x" share the "os" in "mac os x"; a
+        // non-overlapping scan would only ever report one of the two.
+        let trees = vec![extract_literals("mac os"), extract_literals("os x")];
+        let (automaton, atoms) = build(&trees);
+        let present = present_atoms(&automaton, "mac os x");
+
+        assert!(atoms[0].is_candidate(&present), "\"mac os\" should be a candidate");
+        assert!(atoms[1].is_candidate(&present), "\"os x\" should be a candidate");
+    }
+
+    #[test]
+    fn missing_atom_is_not_a_candidate() {
+        let trees = vec![extract_literals("windows")];
+        let (automaton, atoms) = build(&trees);
+        let present = present_atoms(&automaton, "mozilla/5.0 (macintosh)");
+
+        assert!(!atoms[0].is_candidate(&present));
+    }
+
+    #[test]
+    fn alternation_requires_any_branch() {
+        let tree = extract_literals("(?:firefox|chrome)");
+        let (automaton, atoms) = build(&[tree]);
+
+        let firefox_present = present_atoms(&automaton, "mozilla firefox/1.0");
+        assert!(atoms[0].is_candidate(&firefox_present));
+
+        let neither_present = present_atoms(&automaton, "mozilla safari/1.0");
+        assert!(!atoms[0].is_candidate(&neither_present));
+    }
+
+    #[test]
+    fn zero_or_more_quantifier_yields_always() {
+        // No mandatory literal can be derived from `.*`, so the matcher must
+        // always be tried rather than silently skipped.
+        let tree = extract_literals(".*");
+        assert!(matches!(tree, LiteralExpr::Always));
+
+        let (automaton, atoms) = build(&[tree]);
+        let present = present_atoms(&automaton, "anything at all");
+        assert!(atoms[0].is_candidate(&present));
+    }
+
+    #[test]
+    fn unparseable_pattern_yields_always() {
+        let tree = extract_literals("(unbalanced");
+        assert!(matches!(tree, LiteralExpr::Always));
+    }
+}
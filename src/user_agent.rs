@@ -0,0 +1,12 @@
+use derive_more::Display;
+
+/// The browser/user-agent family information parsed from a user agent
+/// string.
+#[derive(Clone, Debug, Default, Display, Eq, PartialEq)]
+#[display(fmt = "{}", family)]
+pub struct UserAgent {
+    pub family: String,
+    pub major: Option<String>,
+    pub minor: Option<String>,
+    pub patch: Option<String>,
+}